@@ -0,0 +1,19 @@
+use jsonwebtoken::{decode, DecodingKey, TokenData, Validation};
+use serde::{Deserialize, Serialize};
+
+/// Claims embedded in a farmhand session JWT
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub user_id: String,
+    pub exp: usize,
+}
+
+/// Decodes and validates a JWT, returning its claims
+pub fn decode_jwt(token: String) -> Result<TokenData<Claims>, jsonwebtoken::errors::Error> {
+    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "changeme".to_string());
+    decode::<Claims>(
+        &token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )
+}
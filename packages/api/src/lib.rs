@@ -0,0 +1,32 @@
+pub mod jwt;
+pub mod middleware;
+pub mod routes;
+
+use std::sync::Arc;
+
+use axum::{middleware::from_fn_with_state, routing::get, Router};
+use metrics_exporter_prometheus::PrometheusHandle;
+use sqlx::{Pool, Postgres};
+
+use middleware::auth::auth_middleware;
+use routes::metrics::metrics_handler;
+use routes::playback::serve_video_asset;
+
+/// Shared state handed to every axum handler
+pub struct AppState {
+    pub db: Pool<Postgres>,
+    pub metrics_handle: PrometheusHandle,
+}
+
+/// Builds the full axum router: `/metrics` is public for scraping, video
+/// playback requires a valid session via `auth_middleware`.
+pub fn app(state: Arc<AppState>) -> Router {
+    let protected = Router::new()
+        .route("/videos/:video_id/*asset_path", get(serve_video_asset))
+        .route_layer(from_fn_with_state(state.clone(), auth_middleware));
+
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .merge(protected)
+        .with_state(state)
+}
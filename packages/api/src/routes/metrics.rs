@@ -0,0 +1,10 @@
+use std::sync::Arc;
+
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+
+/// Renders the process's current Prometheus metrics snapshot for scraping
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> Result<String, StatusCode> {
+    Ok(state.metrics_handle.render())
+}
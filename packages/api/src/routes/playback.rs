@@ -0,0 +1,207 @@
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, Response, StatusCode},
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
+
+use crate::AppState;
+
+/// Serves a video's master playlist, variant playlists, and segment files out of
+/// `get_videos_dir()/<video_id>/<asset_path>`, honoring `Range` requests for segments.
+pub async fn serve_video_asset(
+    State(_state): State<Arc<AppState>>,
+    Path((video_id, asset_path)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    let video_dir = PathBuf::from(get_videos_dir()).join(&video_id);
+    let requested_path = resolve_asset_path(&video_dir, &asset_path)?;
+
+    let mut file = tokio::fs::File::open(&requested_path)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let file_meta = file.metadata().await.map_err(|_| StatusCode::NOT_FOUND)?;
+    let file_len = file_meta.len();
+
+    let content_type = content_type_for(&requested_path);
+    let last_modified = file_meta.modified().ok();
+    let etag = etag_for(file_len, last_modified);
+
+    if let Some(response) = not_modified_response(&headers, &etag) {
+        return Ok(response);
+    }
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_range(v, file_len));
+
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, &etag);
+    if let Some(last_modified) = last_modified {
+        builder = builder.header(header::LAST_MODIFIED, httpdate::fmt_http_date(last_modified));
+    }
+
+    match range {
+        Some((start, end)) if end >= start && end < file_len => {
+            let length = end - start + 1;
+            file.seek(SeekFrom::Start(start))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let mut buffer = vec![0u8; length as usize];
+            file.read_exact(&mut buffer)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {start}-{end}/{file_len}"),
+                )
+                .header(header::CONTENT_LENGTH, length)
+                .body(Body::from(buffer))
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        _ => {
+            let mut buffer = Vec::with_capacity(file_len as usize);
+            file.read_to_end(&mut buffer)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            builder
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, file_len)
+                .body(Body::from(buffer))
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Joins the requested asset path onto the video directory and rejects anything
+/// that escapes it (path traversal via `..`, absolute paths, symlinks elsewhere)
+fn resolve_asset_path(video_dir: &FsPath, asset_path: &str) -> Result<PathBuf, StatusCode> {
+    let joined = video_dir.join(asset_path);
+    let canonical_video_dir = video_dir
+        .canonicalize()
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let canonical = joined.canonicalize().map_err(|_| StatusCode::NOT_FOUND)?;
+
+    if !canonical.starts_with(&canonical_video_dir) {
+        tracing::warn!("playback: rejected path traversal attempt for {asset_path}");
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(canonical)
+}
+
+/// Picks the content type by file extension
+fn content_type_for(path: &FsPath) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("m3u8") => "application/vnd.apple.mpegurl",
+        Some("ts") => "video/mp2t",
+        Some("m4s") | Some("mp4") => "video/mp4",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("vtt") => "text/vtt",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A cheap, stable ETag derived from file size and modification time
+fn etag_for(len: u64, modified: Option<SystemTime>) -> String {
+    let modified_secs = modified
+        .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!("\"{len:x}-{modified_secs:x}\"")
+}
+
+/// Returns a `304 Not Modified` response if the request's `If-None-Match` matches
+fn not_modified_response(headers: &HeaderMap, etag: &str) -> Option<Response<Body>> {
+    let if_none_match = headers.get(header::IF_NONE_MATCH)?.to_str().ok()?;
+    if if_none_match == etag {
+        return Some(
+            Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .header(header::ETAG, etag)
+                .body(Body::empty())
+                .expect("building a 304 response should never fail"),
+        );
+    }
+    None
+}
+
+/// Parses a single-range `Range: bytes=start-end` header into `(start, end)` inclusive bounds
+fn parse_range(header_value: &str, file_len: u64) -> Option<(u64, u64)> {
+    let spec = header_value.strip_prefix("bytes=")?;
+    let (start_str, end_str) = spec.split_once('-')?;
+
+    match (start_str.trim(), end_str.trim()) {
+        ("", "") => None,
+        (start, "") => {
+            let start: u64 = start.parse().ok()?;
+            Some((start, file_len.saturating_sub(1)))
+        }
+        ("", suffix_len) => {
+            let suffix_len: u64 = suffix_len.parse().ok()?;
+            let start = file_len.saturating_sub(suffix_len);
+            Some((start, file_len.saturating_sub(1)))
+        }
+        (start, end) => Some((start.parse().ok()?, end.parse().ok()?)),
+    }
+}
+
+/// Get the directory for where to store videos
+fn get_videos_dir() -> String {
+    std::env::var("VIDEOS_DIR").unwrap_or_else(|_| "videos".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_range_handles_start_and_end() {
+        assert_eq!(parse_range("bytes=0-499", 1000), Some((0, 499)));
+    }
+
+    #[test]
+    fn parse_range_handles_open_ended_start() {
+        assert_eq!(parse_range("bytes=500-", 1000), Some((500, 999)));
+    }
+
+    #[test]
+    fn parse_range_handles_suffix_length() {
+        assert_eq!(parse_range("bytes=-500", 1000), Some((500, 999)));
+    }
+
+    #[test]
+    fn parse_range_rejects_missing_bounds() {
+        assert_eq!(parse_range("bytes=-", 1000), None);
+    }
+
+    #[test]
+    fn parse_range_rejects_missing_prefix() {
+        assert_eq!(parse_range("0-499", 1000), None);
+    }
+
+    #[test]
+    fn parse_range_rejects_non_numeric_bounds() {
+        assert_eq!(parse_range("bytes=abc-def", 1000), None);
+    }
+
+    #[test]
+    fn content_type_for_recognizes_poster_and_vtt_assets() {
+        assert_eq!(content_type_for(FsPath::new("poster.jpg")), "image/jpeg");
+        assert_eq!(content_type_for(FsPath::new("sprite.jpeg")), "image/jpeg");
+        assert_eq!(content_type_for(FsPath::new("thumbnails.vtt")), "text/vtt");
+        assert_eq!(content_type_for(FsPath::new("master.m3u8")), "application/vnd.apple.mpegurl");
+        assert_eq!(content_type_for(FsPath::new("unknown.bin")), "application/octet-stream");
+    }
+}
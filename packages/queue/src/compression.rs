@@ -0,0 +1,264 @@
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use zip::{write::FileOptions, ZipWriter};
+
+/// Which archival strategy to apply to a raw source once its HLS renditions exist
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionMode {
+    /// Wrap the raw file in a zip archive (legacy default, barely shrinks video)
+    Zip,
+    /// Re-encode to an efficient archival codec (HEVC/AV1) at a CRF quality target
+    ArchivalTranscode,
+}
+
+impl CompressionMode {
+    /// Reads `COMPRESSION_MODE` (`"zip"` or `"archival"`), defaulting to zip
+    pub fn from_env() -> Self {
+        match std::env::var("COMPRESSION_MODE").as_deref() {
+            Ok("archival") => CompressionMode::ArchivalTranscode,
+            _ => CompressionMode::Zip,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompressionMode::Zip => "zip",
+            CompressionMode::ArchivalTranscode => "archival_transcode",
+        }
+    }
+}
+
+/// The archived file produced by either compression mode, ready to record on the video
+pub(crate) struct CompressionOutcome {
+    pub(crate) output_path: PathBuf,
+    pub(crate) size_bytes: u64,
+    pub(crate) codec: &'static str,
+}
+
+/// Codec and quality target used for archival transcodes
+const ARCHIVAL_CODEC: &str = "libx265";
+const ARCHIVAL_CRF: &str = "28";
+/// How far the re-encoded duration may drift from the source and still be trusted
+const DURATION_TOLERANCE_SECS: f64 = 1.0;
+
+/// Wraps the raw source file in a zip archive, same as farmhand has always done
+pub(crate) async fn zip_raw_video(
+    raw_video_path: &Path,
+    video_dir: &Path,
+) -> Result<CompressionOutcome, Error> {
+    let zip_path = video_dir.join("raw.zip");
+    let mut zip =
+        ZipWriter::new(fs::File::create(&zip_path).map_err(|e| Error::VideoProcessingError(e.to_string()))?);
+
+    let file_name = raw_video_path
+        .file_name()
+        .ok_or_else(|| Error::VideoProcessingError("Invalid raw video path".to_string()))?
+        .to_string_lossy()
+        .into_owned();
+
+    zip.start_file(&file_name, FileOptions::default())
+        .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+
+    let mut file = File::open(raw_video_path)
+        .await
+        .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+    let mut buffer = vec![0; 1024 * 1024]; // 1MB chunks
+
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        zip.write_all(&buffer[..n])
+            .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+    }
+
+    zip.finish()
+        .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+    drop(file);
+
+    let size_bytes = fs::metadata(&zip_path)
+        .map_err(|e| Error::VideoProcessingError(e.to_string()))?
+        .len();
+
+    Ok(CompressionOutcome {
+        output_path: zip_path,
+        size_bytes,
+        codec: "zip",
+    })
+}
+
+/// Re-encodes the raw source to an efficient archival codec, verifying the result's
+/// duration and video stream before the caller is allowed to delete the original.
+pub(crate) fn archival_transcode(
+    ffmpeg_location: &str,
+    ffprobe_location: &str,
+    raw_video_path: &Path,
+    video_dir: &Path,
+) -> Result<CompressionOutcome, Error> {
+    let source_duration = probe_duration_seconds(ffprobe_location, raw_video_path).ok_or_else(|| {
+        Error::VideoProcessingError("Could not determine source duration".to_string())
+    })?;
+
+    let output_path = video_dir.join("archive.mp4");
+    let status = Command::new(ffmpeg_location)
+        .args([
+            "-y",
+            "-i",
+            raw_video_path
+                .to_str()
+                .expect("raw video path should be valid UTF-8"),
+            "-c:v",
+            ARCHIVAL_CODEC,
+            "-crf",
+            ARCHIVAL_CRF,
+            "-c:a",
+            "copy",
+            output_path
+                .to_str()
+                .expect("output path should be valid UTF-8"),
+        ])
+        .status()
+        .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+
+    if !status.success() {
+        return Err(Error::VideoProcessingError(
+            "ffmpeg failed to produce archival transcode".to_string(),
+        ));
+    }
+
+    verify_archival_transcode(ffprobe_location, &output_path, source_duration)?;
+
+    let size_bytes = fs::metadata(&output_path)
+        .map_err(|e| Error::VideoProcessingError(e.to_string()))?
+        .len();
+
+    Ok(CompressionOutcome {
+        output_path,
+        size_bytes,
+        codec: ARCHIVAL_CODEC,
+    })
+}
+
+/// Confirms the transcode is actually usable before we let the caller delete the source:
+/// its duration must match within tolerance and it must still carry a video stream.
+fn verify_archival_transcode(
+    ffprobe_location: &str,
+    output_path: &Path,
+    source_duration: f64,
+) -> Result<(), Error> {
+    let output_duration = probe_duration_seconds(ffprobe_location, output_path).ok_or_else(|| {
+        Error::VideoProcessingError("Archival transcode has no readable duration".to_string())
+    })?;
+
+    if (output_duration - source_duration).abs() > DURATION_TOLERANCE_SECS {
+        return Err(Error::VideoProcessingError(format!(
+            "Archival transcode duration {output_duration:.2}s differs from source {source_duration:.2}s by more than {DURATION_TOLERANCE_SECS}s"
+        )));
+    }
+
+    if !probe_has_video_stream(ffprobe_location, output_path) {
+        return Err(Error::VideoProcessingError(
+            "Archival transcode has no video stream".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn probe_duration_seconds(ffprobe_location: &str, path: &Path) -> Option<f64> {
+    let output = Command::new(ffprobe_location)
+        .args([
+            "-v",
+            "quiet",
+            "-print_format",
+            "json",
+            "-show_entries",
+            "format=duration",
+            path.to_str()?,
+        ])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let raw = String::from_utf8(output.stdout).ok()?;
+    let parsed: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    parsed
+        .get("format")?
+        .get("duration")?
+        .as_str()?
+        .parse::<f64>()
+        .ok()
+}
+
+fn probe_has_video_stream(ffprobe_location: &str, path: &Path) -> bool {
+    let Some(path_str) = path.to_str() else {
+        return false;
+    };
+    let output = Command::new(ffprobe_location)
+        .args([
+            "-v",
+            "quiet",
+            "-print_format",
+            "json",
+            "-show_streams",
+            path_str,
+        ])
+        .output();
+
+    let Ok(output) = output else {
+        return false;
+    };
+    if !output.status.success() {
+        return false;
+    }
+
+    let Ok(raw) = String::from_utf8(output.stdout) else {
+        return false;
+    };
+    let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&raw) else {
+        return false;
+    };
+
+    parsed
+        .get("streams")
+        .and_then(|s| s.as_array())
+        .is_some_and(|streams| {
+            streams
+                .iter()
+                .any(|s| s.get("codec_type").and_then(|c| c.as_str()) == Some("video"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compression_mode_round_trips_through_json() {
+        for mode in [CompressionMode::Zip, CompressionMode::ArchivalTranscode] {
+            let json = serde_json::to_value(mode).expect("serialize");
+            let decoded: CompressionMode = serde_json::from_value(json).expect("deserialize");
+            assert_eq!(decoded, mode);
+        }
+    }
+
+    #[test]
+    fn compression_mode_from_env_defaults_to_zip() {
+        std::env::remove_var("COMPRESSION_MODE");
+        assert_eq!(CompressionMode::from_env(), CompressionMode::Zip);
+    }
+}
@@ -0,0 +1,14 @@
+use uuid::Uuid;
+
+/// Errors surfaced by the queue worker and its job handlers
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("database error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("video processing error: {0}")]
+    VideoProcessingError(String),
+
+    #[error("job {0} not found")]
+    JobNotFound(Uuid),
+}
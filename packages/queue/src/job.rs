@@ -0,0 +1,33 @@
+use uuid::Uuid;
+
+use crate::queue::Message;
+
+/// Lifecycle status of a row in the `jobs` table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostgresJobStatus {
+    Queued,
+    Processing,
+    Completed,
+    Failed,
+}
+
+impl PostgresJobStatus {
+    /// The string stored in the `jobs.status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostgresJobStatus::Queued => "queued",
+            PostgresJobStatus::Processing => "processing",
+            PostgresJobStatus::Completed => "completed",
+            PostgresJobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// A single claimed unit of work pulled off the queue
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: Uuid,
+    pub message: Message,
+    pub failed_attempts: i32,
+    pub max_attempts: i32,
+}
@@ -0,0 +1,12 @@
+pub mod compression;
+pub mod error;
+pub mod job;
+pub mod metrics;
+pub mod queue;
+pub mod runner;
+pub mod thumbnails;
+
+pub use error::Error;
+pub use job::{Job, PostgresJobStatus};
+pub use queue::{Message, PostgresQueue, Queue};
+pub use runner::run_worker;
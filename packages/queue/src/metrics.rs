@@ -0,0 +1,62 @@
+use crate::queue::Message;
+use metrics::{counter, gauge, histogram};
+use std::time::Instant;
+
+/// Stable label for a job's message type, used across all job metrics
+pub(crate) fn message_type_label(message: &Message) -> &'static str {
+    match message {
+        Message::ProcessRawVideoIntoStream { .. } => "process_raw_video_into_stream",
+        Message::CompressRawVideo { .. } => "compress_raw_video",
+        Message::GenerateThumbnails { .. } => "generate_thumbnails",
+        _ => "unknown",
+    }
+}
+
+/// Times a single job's processing and records its outcome on drop, so a panic
+/// or an early `return` still gets counted instead of silently vanishing.
+pub(crate) struct JobTimer {
+    message_type: &'static str,
+    start: Instant,
+    completed: bool,
+}
+
+impl JobTimer {
+    /// Starts timing a job and increments the started counter
+    pub(crate) fn start(message_type: &'static str) -> Self {
+        counter!("farmhand_jobs_started_total", "message_type" => message_type).increment(1);
+        Self {
+            message_type,
+            start: Instant::now(),
+            completed: false,
+        }
+    }
+
+    /// Marks the job as having completed successfully; call right before returning `Ok`
+    pub(crate) fn mark_completed(&mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for JobTimer {
+    fn drop(&mut self) {
+        histogram!(
+            "farmhand_job_duration_seconds",
+            "message_type" => self.message_type,
+            "completed" => self.completed.to_string(),
+        )
+        .record(self.start.elapsed().as_secs_f64());
+
+        if self.completed {
+            counter!("farmhand_jobs_completed_total", "message_type" => self.message_type)
+                .increment(1);
+        } else {
+            counter!("farmhand_jobs_failed_total", "message_type" => self.message_type)
+                .increment(1);
+        }
+    }
+}
+
+/// Publishes the current queue depth; called once per `run_worker` loop iteration
+pub(crate) fn record_queue_depth(depth: i64) {
+    gauge!("farmhand_queue_depth").set(depth as f64);
+}
@@ -0,0 +1,190 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres, Row};
+use uuid::Uuid;
+
+use crate::compression::CompressionMode;
+use crate::error::Error;
+use crate::job::{Job, PostgresJobStatus};
+
+/// Default number of attempts a job gets before `run_worker` gives up on it
+const DEFAULT_MAX_ATTEMPTS: i32 = 5;
+
+/// The work a queued job represents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Message {
+    ProcessRawVideoIntoStream {
+        video_id: Uuid,
+    },
+    CompressRawVideo {
+        video_id: Uuid,
+        mode: Option<CompressionMode>,
+    },
+    GenerateThumbnails {
+        video_id: Uuid,
+    },
+}
+
+/// Backing store for the job queue: claiming, completing, retrying, and recovering jobs
+#[async_trait]
+pub trait Queue: Send + Sync {
+    /// Claims up to `limit` queued jobs that are due to run
+    async fn pull(&self, limit: i32) -> Result<Vec<Job>, Error>;
+    /// Enqueues a new job, optionally scheduled for the future
+    async fn push(
+        &self,
+        message: Message,
+        status: PostgresJobStatus,
+        scheduled_at: Option<DateTime<Utc>>,
+    ) -> Result<Uuid, Error>;
+    /// Removes a job that completed successfully
+    async fn delete_job(&self, id: Uuid) -> Result<(), Error>;
+    /// Marks a job permanently failed once its attempts are exhausted
+    async fn fail_job(&self, id: Uuid) -> Result<(), Error>;
+    /// Requeues a job for another attempt at `scheduled_at`, bumping `failed_attempts`
+    async fn retry_job(&self, id: Uuid, scheduled_at: DateTime<Utc>) -> Result<(), Error>;
+    /// Refreshes a claimed job's lock so recovery passes don't treat it as abandoned
+    async fn heartbeat_job(&self, id: Uuid) -> Result<(), Error>;
+    /// Requeues jobs still marked `processing` whose lock is older than `stale_threshold`
+    async fn recover_stale_jobs(&self, stale_threshold: Duration) -> Result<u64, Error>;
+    /// Number of jobs currently waiting to run
+    async fn queue_depth(&self) -> Result<i64, Error>;
+}
+
+/// Postgres-backed implementation of [`Queue`]
+pub struct PostgresQueue {
+    pool: Pool<Postgres>,
+}
+
+impl PostgresQueue {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl Queue for PostgresQueue {
+    async fn pull(&self, limit: i32) -> Result<Vec<Job>, Error> {
+        let rows = sqlx::query(
+            "UPDATE jobs
+             SET status = 'processing', locked_at = NOW(), updated_at = NOW()
+             WHERE id IN (
+                 SELECT id FROM jobs
+                 WHERE status = 'queued' AND scheduled_at <= NOW()
+                 ORDER BY scheduled_at
+                 LIMIT $1
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING id, message, failed_attempts, max_attempts",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let message_json: serde_json::Value = row.try_get("message")?;
+                let message: Message = serde_json::from_value(message_json)
+                    .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+
+                Ok(Job {
+                    id: row.try_get("id")?,
+                    message,
+                    failed_attempts: row.try_get("failed_attempts")?,
+                    max_attempts: row.try_get("max_attempts")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn push(
+        &self,
+        message: Message,
+        status: PostgresJobStatus,
+        scheduled_at: Option<DateTime<Utc>>,
+    ) -> Result<Uuid, Error> {
+        let id = Uuid::new_v4();
+        let message_json = serde_json::to_value(&message)
+            .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+        let scheduled_at = scheduled_at.unwrap_or_else(Utc::now);
+
+        sqlx::query(
+            "INSERT INTO jobs (id, message, status, scheduled_at, failed_attempts, max_attempts, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, 0, $5, NOW(), NOW())",
+        )
+        .bind(id)
+        .bind(message_json)
+        .bind(status.as_str())
+        .bind(scheduled_at)
+        .bind(DEFAULT_MAX_ATTEMPTS)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn delete_job(&self, id: Uuid) -> Result<(), Error> {
+        sqlx::query("DELETE FROM jobs WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn fail_job(&self, id: Uuid) -> Result<(), Error> {
+        sqlx::query(
+            "UPDATE jobs SET status = 'failed', updated_at = NOW() WHERE id = $1",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn retry_job(&self, id: Uuid, scheduled_at: DateTime<Utc>) -> Result<(), Error> {
+        sqlx::query(
+            "UPDATE jobs
+             SET status = 'queued',
+                 failed_attempts = failed_attempts + 1,
+                 scheduled_at = $1,
+                 locked_at = NULL,
+                 updated_at = NOW()
+             WHERE id = $2",
+        )
+        .bind(scheduled_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn heartbeat_job(&self, id: Uuid) -> Result<(), Error> {
+        sqlx::query("UPDATE jobs SET locked_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn recover_stale_jobs(&self, stale_threshold: Duration) -> Result<u64, Error> {
+        let cutoff = Utc::now() - stale_threshold;
+        let result = sqlx::query(
+            "UPDATE jobs
+             SET status = 'queued', locked_at = NULL, updated_at = NOW()
+             WHERE status = 'processing' AND locked_at < $1",
+        )
+        .bind(cutoff)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn queue_depth(&self) -> Result<i64, Error> {
+        let row = sqlx::query("SELECT COUNT(*) AS count FROM jobs WHERE status = 'queued'")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.try_get("count")?)
+    }
+}
@@ -1,20 +1,269 @@
+use crate::compression::{self, CompressionMode, CompressionOutcome};
 use crate::error::Error;
-use crate::job::PostgresJobStatus;
-use crate::queue::{Job, Message, Queue};
+use crate::job::{Job, PostgresJobStatus};
+use crate::metrics::{message_type_label, record_queue_depth, JobTimer};
+use crate::queue::{Message, Queue};
+use crate::thumbnails;
+use chrono::{Duration as ChronoDuration, Utc};
 use db::Video;
 use futures::{stream, StreamExt};
+use rand::Rng;
+use serde::Deserialize;
 use sqlx::{Pool, Postgres};
 use std::fs;
-use std::io::Write;
+use std::process::Command;
 use std::{path::PathBuf, sync::Arc, time::Duration};
-use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use tokio_util::sync::CancellationToken;
 use vod::{HLSConverter, Quality};
-use zip::{write::FileOptions, ZipWriter};
 
-/// Runs a loop that pulls jobs from the queue and runs <concurrency> jobs each loop
-pub async fn run_worker(queue: Arc<dyn Queue>, concurrency: usize, db_conn: &Pool<Postgres>) {
+/// A standard bitrate ladder rung before it has been checked against the source.
+/// `short_edge` is the rung's nominal resolution along whichever source dimension is
+/// smaller (height for landscape, width for portrait), so the same template works for
+/// both orientations instead of hardcoding landscape width/height pairs.
+struct QualityTemplate {
+    short_edge: u32,
+    bitrate_kbps: u32,
+    label: &'static str,
+}
+
+/// Descending template of renditions we'd like to offer, tallest first
+const QUALITY_LADDER_TEMPLATE: &[QualityTemplate] = &[
+    QualityTemplate {
+        short_edge: 2160,
+        bitrate_kbps: 16000,
+        label: "2160p",
+    },
+    QualityTemplate {
+        short_edge: 1080,
+        bitrate_kbps: 5000,
+        label: "1080p",
+    },
+    QualityTemplate {
+        short_edge: 720,
+        bitrate_kbps: 2800,
+        label: "720p",
+    },
+    QualityTemplate {
+        short_edge: 480,
+        bitrate_kbps: 1400,
+        label: "480p",
+    },
+    QualityTemplate {
+        short_edge: 360,
+        bitrate_kbps: 800,
+        label: "360p",
+    },
+];
+
+/// The fixed ladder used when we can't probe (or don't trust) the source
+fn fallback_quality_ladder() -> Vec<Quality> {
+    vec![
+        Quality::new(1920, 1080, "5000k", "1080p"),
+        Quality::new(1280, 720, "2800k", "720p"),
+        Quality::new(854, 480, "1400k", "480p"),
+    ]
+}
+
+/// Relevant bits of the source video stream, parsed out of `ffprobe`'s JSON output
+#[derive(Debug, Clone, PartialEq)]
+struct SourceMetadata {
+    width: u32,
+    height: u32,
+    bit_rate_kbps: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeStream {
+    codec_type: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    bit_rate: Option<String>,
+}
+
+impl SourceMetadata {
+    /// Parse ffprobe's `-show_streams` JSON, picking out the first video stream
+    fn from_ffprobe_json(raw: &str) -> Option<Self> {
+        let parsed: FfprobeOutput = serde_json::from_str(raw).ok()?;
+        let video_stream = parsed
+            .streams
+            .into_iter()
+            .find(|s| s.codec_type == "video")?;
+
+        let width = video_stream.width?;
+        let height = video_stream.height?;
+        let bit_rate_kbps = video_stream
+            .bit_rate
+            .and_then(|b| b.parse::<u64>().ok())
+            .map(|bps| (bps / 1000) as u32);
+
+        Some(SourceMetadata {
+            width,
+            height,
+            bit_rate_kbps,
+        })
+    }
+
+    /// Rough bitrate estimate for sources that don't report one, based on resolution
+    fn estimated_bit_rate_kbps(&self) -> u32 {
+        QUALITY_LADDER_TEMPLATE
+            .iter()
+            .min_by_key(|t| (t.short_edge as i64 - self.short_edge() as i64).abs())
+            .map(|t| t.bitrate_kbps)
+            .unwrap_or(1400)
+    }
+
+    /// Whether the source is taller than it is wide
+    fn is_portrait(&self) -> bool {
+        self.height > self.width
+    }
+
+    /// The source's resolution along whichever dimension a quality rung is sized by
+    /// (height for landscape, width for portrait) — see `QualityTemplate::short_edge`
+    fn short_edge(&self) -> u32 {
+        self.width.min(self.height)
+    }
+
+    /// The source's resolution along the other, longer dimension
+    fn long_edge(&self) -> u32 {
+        self.width.max(self.height)
+    }
+}
+
+/// Shells out to ffprobe to inspect the source video's streams
+fn probe_source_video(ffprobe_location: &str, path: &str) -> Option<SourceMetadata> {
+    let output = Command::new(ffprobe_location)
+        .args([
+            "-v",
+            "quiet",
+            "-print_format",
+            "json",
+            "-show_streams",
+            path,
+        ])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let raw = String::from_utf8(output.stdout).ok()?;
+    SourceMetadata::from_ffprobe_json(&raw)
+}
+
+/// Builds a `Quality` ladder tailored to the source: drops rungs that would upscale,
+/// orients rungs to the source's own aspect ratio (so portrait uploads get portrait
+/// renditions, not bogus landscape-dimensioned ones), and clamps each rung's bitrate
+/// to the rung above it so lower rungs are never more expensive than the rung they're
+/// supposed to be a cheaper alternative to.
+fn build_quality_ladder(source: Option<&SourceMetadata>) -> Vec<Quality> {
+    let Some(source) = source else {
+        return fallback_quality_ladder();
+    };
+
+    let source_short_edge = source.short_edge();
+    let source_long_edge = source.long_edge();
+    let source_bit_rate_kbps = source
+        .bit_rate_kbps
+        .unwrap_or_else(|| source.estimated_bit_rate_kbps());
+
+    let rungs: Vec<&QualityTemplate> = QUALITY_LADDER_TEMPLATE
+        .iter()
+        .filter(|t| t.short_edge <= source_short_edge)
+        .collect();
+
+    // The source is smaller than our smallest template rung (e.g. a sub-360p upload):
+    // emit a single rung at the source's own resolution instead of upscaling it to
+    // the smallest template's dimensions.
+    let source_rung = QualityTemplate {
+        short_edge: source_short_edge,
+        bitrate_kbps: source_bit_rate_kbps,
+        label: "source",
+    };
+    let rungs: Vec<&QualityTemplate> = if rungs.is_empty() {
+        vec![&source_rung]
+    } else {
+        rungs
+    };
+
+    let mut previous_bitrate_kbps = source_bit_rate_kbps;
+    rungs
+        .into_iter()
+        .map(|t| {
+            // Clamp against the rung above (or the source bitrate for the top rung) so
+            // a low-bitrate source can never produce a "cheaper" rung that's actually
+            // more expensive than the rung above it.
+            let bitrate_kbps = t.bitrate_kbps.min(previous_bitrate_kbps);
+            previous_bitrate_kbps = bitrate_kbps;
+
+            let short_edge = t.short_edge.min(source_short_edge);
+            let long_edge = scale_long_edge(short_edge, source_short_edge, source_long_edge);
+            let (width, height) = if source.is_portrait() {
+                (short_edge, long_edge)
+            } else {
+                (long_edge, short_edge)
+            };
+            Quality::new(width, height, &format!("{bitrate_kbps}k"), t.label)
+        })
+        .collect()
+}
+
+/// Scales a rung's long edge proportionally to the source's aspect ratio, rounded up
+/// to an even number since most encoders reject odd chroma-subsampled dimensions
+fn scale_long_edge(short_edge: u32, source_short_edge: u32, source_long_edge: u32) -> u32 {
+    let scaled = (short_edge as u64 * source_long_edge as u64) / source_short_edge.max(1) as u64;
+    let scaled = scaled as u32;
+    scaled + (scaled % 2)
+}
+
+/// How often an in-flight job refreshes its lock so it isn't mistaken for abandoned
+const HEARTBEAT_INTERVAL_SECS: u64 = 60;
+
+/// Jobs left locked longer than this with no heartbeat (deploy killed mid-job, crash,
+/// etc.) are assumed abandoned and requeued. Kept well above `HEARTBEAT_INTERVAL_SECS`
+/// so a long-running transcode that's still heartbeating is never recovered out from
+/// under a healthy worker.
+const STALE_JOB_THRESHOLD_SECS: i64 = 10 * 60;
+
+/// Requeues jobs that were claimed by a worker that never reported back, based on
+/// their `locked_at`/heartbeat timestamp. Run once on startup before the main loop.
+async fn recover_stale_jobs(queue: &dyn Queue) -> Result<(), Error> {
+    let stale_threshold = ChronoDuration::seconds(STALE_JOB_THRESHOLD_SECS);
+    let recovered = queue.recover_stale_jobs(stale_threshold).await?;
+    if recovered > 0 {
+        tracing::warn!(
+            "run_worker: recovered {} stale in-progress job(s) on startup",
+            recovered
+        );
+    }
+    Ok(())
+}
+
+/// Runs a loop that pulls jobs from the queue and runs <concurrency> jobs each loop.
+/// Stops pulling new batches once `shutdown` is cancelled, letting the in-flight
+/// batch drain before returning.
+pub async fn run_worker(
+    queue: Arc<dyn Queue>,
+    concurrency: usize,
+    db_conn: &Pool<Postgres>,
+    shutdown: CancellationToken,
+) {
+    if let Err(err) = recover_stale_jobs(queue.as_ref()).await {
+        tracing::error!("run_worker: error recovering stale jobs on startup: {}", err);
+    }
+
     loop {
+        if shutdown.is_cancelled() {
+            tracing::info!("run_worker: shutdown requested, stopping after last batch drained");
+            break;
+        }
+
         // Pulls jobs from the queue
         let jobs = match queue.pull(concurrency as i32).await {
             Ok(jobs) => jobs,
@@ -30,16 +279,59 @@ pub async fn run_worker(queue: Arc<dyn Queue>, concurrency: usize, db_conn: &Poo
             tracing::debug!("Fetched {} jobs", number_of_jobs);
         }
 
+        match queue.queue_depth().await {
+            Ok(depth) => record_queue_depth(depth),
+            Err(err) => tracing::error!("runner: error sampling queue depth {}", err),
+        }
+
         stream::iter(jobs)
             .for_each_concurrent(concurrency, |job| async {
                 tracing::debug!("Starting job {}", job.id);
                 let job_id = job.id;
+                let failed_attempts = job.failed_attempts;
+                let max_attempts = job.max_attempts;
+
+                // Keep the job's lock fresh while it's in flight so a long-running
+                // transcode isn't mistaken for abandoned by a later recovery pass
+                let heartbeat_queue = queue.clone();
+                let heartbeat = tokio::spawn(async move {
+                    loop {
+                        tokio::time::sleep(Duration::from_secs(HEARTBEAT_INTERVAL_SECS)).await;
+                        if let Err(err) = heartbeat_queue.heartbeat_job(job_id).await {
+                            tracing::error!(
+                                "run_worker: failed to refresh lock for job({}): {}",
+                                job_id,
+                                err
+                            );
+                        }
+                    }
+                });
 
-                let res = match handle_job(queue.clone(), job, db_conn).await {
+                let job_result = handle_job(queue.clone(), job, db_conn).await;
+                heartbeat.abort();
+
+                let res = match job_result {
                     Ok(_) => queue.delete_job(job_id).await,
                     Err(err) => {
                         tracing::error!("run_worker: handling job({}): {}", job_id, &err);
-                        queue.fail_job(job_id).await
+                        if failed_attempts + 1 < max_attempts {
+                            let scheduled_at = Utc::now() + retry_delay(failed_attempts);
+                            tracing::warn!(
+                                "run_worker: retrying job({}) at {} (attempt {}/{})",
+                                job_id,
+                                scheduled_at,
+                                failed_attempts + 1,
+                                max_attempts
+                            );
+                            queue.retry_job(job_id, scheduled_at).await
+                        } else {
+                            tracing::error!(
+                                "run_worker: job({}) exhausted {} attempts, failing",
+                                job_id,
+                                max_attempts
+                            );
+                            queue.fail_job(job_id).await
+                        }
                     }
                 };
 
@@ -49,13 +341,20 @@ pub async fn run_worker(queue: Arc<dyn Queue>, concurrency: usize, db_conn: &Poo
             })
             .await;
 
-        tokio::time::sleep(Duration::from_millis(125)).await;
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_millis(125)) => {}
+            _ = shutdown.cancelled() => {
+                tracing::info!("run_worker: shutdown requested during idle sleep");
+                break;
+            }
+        }
     }
 }
 
 /// Individually processes a single job, based on its Job message type
 async fn handle_job(queue: Arc<dyn Queue>, job: Job, db: &Pool<Postgres>) -> Result<(), Error> {
     tracing::debug!("Got job of type {:?}", &job.message);
+    let mut timer = JobTimer::start(message_type_label(&job.message));
     match job.message {
         Message::ProcessRawVideoIntoStream { video_id } => {
             tracing::info!("Start video processing for video_id {video_id}");
@@ -87,12 +386,16 @@ async fn handle_job(queue: Arc<dyn Queue>, job: Job, db: &Pool<Postgres>) -> Res
             )
             .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
 
-            // Define quality levels
-            let qualities = vec![
-                Quality::new(1920, 1080, "5000k", "1080p"),
-                Quality::new(1280, 720, "2800k", "720p"),
-                Quality::new(854, 480, "1400k", "480p"),
-            ];
+            // Probe the source to build a ladder that never upscales and doesn't
+            // waste bitrate on rungs above what the source actually has
+            let ffprobe_location = get_ffprobe_location();
+            let source_metadata = probe_source_video(&ffprobe_location, &video.raw_video_path);
+            if source_metadata.is_none() {
+                tracing::warn!(
+                    "Could not probe source video {video_id}, falling back to fixed ladder"
+                );
+            }
+            let qualities = build_quality_ladder(source_metadata.as_ref());
 
             // Process the video
             converter
@@ -119,19 +422,79 @@ async fn handle_job(queue: Arc<dyn Queue>, job: Job, db: &Pool<Postgres>) -> Res
                 .push(
                     Message::CompressRawVideo {
                         video_id: video_id.clone(),
+                        mode: None,
                     },
                     PostgresJobStatus::Queued,
                     Some(scheduled_time),
                 )
                 .await?;
 
+            // Also queue up poster/scrubbing thumbnail generation
+            queue
+                .push(
+                    Message::GenerateThumbnails {
+                        video_id: video_id.clone(),
+                    },
+                    PostgresJobStatus::Queued,
+                    None,
+                )
+                .await?;
+
             tracing::info!(
-                "Successfully processed video {} and queued compression job",
+                "Successfully processed video {} and queued compression and thumbnail jobs",
                 &video_id
             );
         }
-        Message::CompressRawVideo { video_id } => {
-            tracing::info!("Start video compression for video_id {video_id}");
+        Message::GenerateThumbnails { video_id } => {
+            tracing::info!("Start thumbnail generation for video_id {video_id}");
+
+            let video = sqlx::query_as::<_, Video>("SELECT * FROM videos WHERE id = $1")
+                .bind(&video_id)
+                .fetch_one(db)
+                .await?;
+
+            let output_dir = PathBuf::from(get_videos_dir()).join(&video_id.to_string());
+            let ffmpeg_location = get_ffmpeg_location();
+            let ffprobe_location = get_ffprobe_location();
+            let raw_video_path = video.raw_video_path.clone();
+
+            // generate_thumbnails shells out to ffmpeg/ffprobe synchronously; run it on
+            // the blocking pool so it doesn't stall the tokio executor thread this
+            // worker's other concurrent jobs are running on
+            let artifacts = tokio::task::spawn_blocking(move || {
+                thumbnails::generate_thumbnails(
+                    &ffmpeg_location,
+                    &ffprobe_location,
+                    &raw_video_path,
+                    &output_dir,
+                )
+            })
+            .await
+            .map_err(|e| Error::VideoProcessingError(format!("thumbnail generation task panicked: {e}")))??;
+
+            sqlx::query(
+                "UPDATE videos SET
+                    poster_path = $1,
+                    sprite_path = $2,
+                    sprite_vtt_path = $3,
+                    updated_at = NOW()
+                WHERE id = $4",
+            )
+            .bind(artifacts.poster_path.to_str().unwrap())
+            .bind(artifacts.sprite_path.to_str().unwrap())
+            .bind(artifacts.vtt_path.to_str().unwrap())
+            .bind(&video_id)
+            .execute(db)
+            .await?;
+
+            tracing::info!("Successfully generated thumbnails for video {}", &video_id);
+        }
+        Message::CompressRawVideo { video_id, mode } => {
+            let compression_mode = mode.unwrap_or_else(CompressionMode::from_env);
+            tracing::info!(
+                "Start video compression for video_id {video_id} (mode: {})",
+                compression_mode.as_str()
+            );
 
             // Update video compression status to compressing
             sqlx::query(
@@ -155,74 +518,73 @@ async fn handle_job(queue: Arc<dyn Queue>, job: Job, db: &Pool<Postgres>) -> Res
                 fs::create_dir_all(&video_dir)
                     .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
 
-                let zip_path = video_dir.join("raw.zip");
-                let mut zip = ZipWriter::new(
-                    fs::File::create(&zip_path)
-                        .map_err(|e| Error::VideoProcessingError(e.to_string()))?,
-                );
-
                 let raw_video_path = PathBuf::from(&video.raw_video_path);
-                let file_name = raw_video_path
-                    .file_name()
-                    .ok_or_else(|| {
-                        Error::VideoProcessingError("Invalid raw video path".to_string())
-                    })?
-                    .to_string_lossy()
-                    .into_owned();
-
-                zip.start_file(&file_name, FileOptions::default())
-                    .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
 
-                // Read the file in chunks
-                let mut file = File::open(&raw_video_path)
-                    .await
-                    .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
-                let mut buffer = vec![0; 1024 * 1024]; // 1MB chunks
-
-                loop {
-                    let n = file
-                        .read(&mut buffer)
+                let outcome = match compression_mode {
+                    CompressionMode::Zip => compression::zip_raw_video(&raw_video_path, &video_dir).await?,
+                    CompressionMode::ArchivalTranscode => {
+                        let ffmpeg_location = get_ffmpeg_location();
+                        let ffprobe_location = get_ffprobe_location();
+                        let raw_video_path = raw_video_path.clone();
+                        let video_dir = video_dir.clone();
+                        // archival_transcode shells out to ffmpeg synchronously and can
+                        // run for minutes re-encoding the full source; run it on the
+                        // blocking pool so it doesn't starve other jobs this worker is
+                        // driving concurrently
+                        tokio::task::spawn_blocking(move || {
+                            compression::archival_transcode(
+                                &ffmpeg_location,
+                                &ffprobe_location,
+                                &raw_video_path,
+                                &video_dir,
+                            )
+                        })
                         .await
-                        .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
-                    if n == 0 {
-                        break;
+                        .map_err(|e| {
+                            Error::VideoProcessingError(format!(
+                                "archival transcode task panicked: {e}"
+                            ))
+                        })??
                     }
-                    zip.write_all(&buffer[..n])
-                        .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
-                }
-
-                zip.finish()
-                    .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
-
-                // Close file handle before trying to remove
-                drop(file);
+                };
 
-                // Remove the original raw video file
+                // Remove the original raw video file now that the archive is written
+                // (archival transcodes were verified above; zip archives are not checked)
                 tokio::fs::remove_file(&raw_video_path).await.map_err(|e| {
                     Error::VideoProcessingError(format!("Failed to remove raw video: {}", e))
                 })?;
 
-                Ok::<PathBuf, Error>(zip_path)
+                Ok::<CompressionOutcome, Error>(outcome)
             }
             .await;
 
             match compression_result {
-                Ok(zip_path) => {
+                Ok(outcome) => {
                     // Update the video record with success status and compressed file path
                     sqlx::query(
                         "UPDATE videos SET
                                     compression_status = 'completed',
                                     compressed_video_path = $1,
+                                    compression_mode = $2,
+                                    compressed_codec = $3,
+                                    compressed_size_bytes = $4,
                                     raw_video_path = NULL,
                                     updated_at = NOW()
-                                WHERE id = $2",
+                                WHERE id = $5",
                     )
-                    .bind(zip_path.to_str().unwrap())
+                    .bind(outcome.output_path.to_str().unwrap())
+                    .bind(compression_mode.as_str())
+                    .bind(outcome.codec)
+                    .bind(outcome.size_bytes as i64)
                     .bind(&video_id)
                     .execute(db)
                     .await?;
 
-                    tracing::info!("Successfully compressed video {}", &video_id);
+                    tracing::info!(
+                        "Successfully compressed video {} ({})",
+                        &video_id,
+                        compression_mode.as_str()
+                    );
                 }
                 Err(err) => {
                     // Update the video record with failed status
@@ -244,15 +606,150 @@ async fn handle_job(queue: Arc<dyn Queue>, job: Job, db: &Pool<Postgres>) -> Res
         _ => tracing::warn!("Unhandled job message passed"),
     }
 
+    timer.mark_completed();
     Ok(())
 }
 
+/// Base delay for the first retry; doubled for each subsequent attempt
+const RETRY_BASE_DELAY_SECS: i64 = 30;
+/// Cap on the exponential backoff, however many attempts have failed; jitter is added on top
+const RETRY_MAX_DELAY_SECS: i64 = 60 * 60;
+
+/// Exponential backoff with jitter: `base * 2^failed_attempts`, capped, plus up to
+/// 25% random jitter so retries from a batch failure don't all land at once
+fn retry_delay(failed_attempts: i32) -> ChronoDuration {
+    let exponent = failed_attempts.max(0).min(20) as u32;
+    let backoff_secs = RETRY_BASE_DELAY_SECS
+        .saturating_mul(1i64 << exponent)
+        .min(RETRY_MAX_DELAY_SECS);
+    let jitter_secs = rand::thread_rng().gen_range(0..=backoff_secs / 4);
+    ChronoDuration::seconds(backoff_secs + jitter_secs)
+}
+
 /// Get the path to ffmpeg
 fn get_ffmpeg_location() -> String {
     std::env::var("FFMPEG_LOCATION").unwrap_or_else(|_| "/usr/bin/ffmpeg".to_string())
 }
 
+/// Get the path to ffprobe
+fn get_ffprobe_location() -> String {
+    std::env::var("FFPROBE_LOCATION").unwrap_or_else(|_| "/usr/bin/ffprobe".to_string())
+}
+
 /// Get the directory for where to store videos
 fn get_videos_dir() -> String {
     std::env::var("VIDEOS_DIR").unwrap_or_else(|_| "videos".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_source(width: u32, height: u32, bit_rate_kbps: Option<u32>) -> SourceMetadata {
+        SourceMetadata {
+            width,
+            height,
+            bit_rate_kbps,
+        }
+    }
+
+    #[test]
+    fn ladder_drops_rungs_above_source_resolution() {
+        let source = make_source(1280, 720, Some(2500));
+        let ladder = build_quality_ladder(Some(&source));
+
+        assert_eq!(ladder.len(), 3, "expected 720p, 480p and 360p rungs only");
+        assert_eq!(ladder[0].height, 720);
+        assert_eq!(ladder[1].height, 480);
+    }
+
+    #[test]
+    fn ladder_bitrate_clamp_cascades_for_low_bitrate_sources() {
+        // A common low-bitrate 720p web upload: well below even the 480p template's
+        // raw bitrate, so every rung must clamp down to the source bitrate, not just
+        // the top one.
+        let source = make_source(1280, 720, Some(500));
+        let ladder = build_quality_ladder(Some(&source));
+
+        assert_eq!(ladder.len(), 3, "expected 720p, 480p and 360p rungs only");
+        assert_eq!(ladder[0].bitrate, "500k");
+        assert_eq!(
+            ladder[1].bitrate, "500k",
+            "lower rungs must never end up costlier than the rung above them"
+        );
+    }
+
+    #[test]
+    fn ladder_never_upscales_a_sub_360p_source() {
+        // Smaller than even the 360p template rung
+        let source = make_source(320, 240, Some(500));
+        let ladder = build_quality_ladder(Some(&source));
+
+        assert_eq!(ladder.len(), 1, "tiny sources should get exactly one rung");
+        assert_eq!(ladder[0].width, 320);
+        assert_eq!(ladder[0].height, 240, "must not upscale to the 360p template rung");
+    }
+
+    #[test]
+    fn ladder_orients_rungs_to_a_portrait_source() {
+        // A 1080x1920 portrait source should get portrait-oriented rungs, not the
+        // template's fixed landscape dimensions
+        let source = make_source(1080, 1920, Some(4000));
+        let ladder = build_quality_ladder(Some(&source));
+
+        for quality in &ladder {
+            assert!(
+                quality.height > quality.width,
+                "expected a portrait rendition, got {}x{}",
+                quality.width,
+                quality.height
+            );
+        }
+        let top = &ladder[0];
+        assert_eq!(top.height, 1920);
+        assert_eq!(top.width, 1080);
+    }
+
+    #[test]
+    fn ladder_estimates_bitrate_when_source_does_not_report_one() {
+        let source = make_source(1280, 720, None);
+        let ladder = build_quality_ladder(Some(&source));
+
+        assert_eq!(ladder[0].height, 720);
+        assert_eq!(ladder[0].bitrate, "2800k");
+    }
+
+    #[test]
+    fn ladder_falls_back_to_fixed_ladder_without_source_metadata() {
+        let ladder = build_quality_ladder(None);
+        assert_eq!(ladder.len(), fallback_quality_ladder().len());
+    }
+
+    #[test]
+    fn source_metadata_parses_ffprobe_json() {
+        let raw = r#"{"streams":[
+            {"codec_type":"audio"},
+            {"codec_type":"video","width":1920,"height":1080,"bit_rate":"5000000"}
+        ]}"#;
+        let parsed = SourceMetadata::from_ffprobe_json(raw).expect("should parse video stream");
+        assert_eq!(parsed.width, 1920);
+        assert_eq!(parsed.height, 1080);
+        assert_eq!(parsed.bit_rate_kbps, Some(5000));
+    }
+
+    #[test]
+    fn source_metadata_rejects_corrupt_json() {
+        assert!(SourceMetadata::from_ffprobe_json("not json").is_none());
+        assert!(SourceMetadata::from_ffprobe_json(r#"{"streams":[]}"#).is_none());
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially_and_caps() {
+        let first = retry_delay(0);
+        assert!(first.num_seconds() >= RETRY_BASE_DELAY_SECS);
+        assert!(first.num_seconds() <= RETRY_BASE_DELAY_SECS + RETRY_BASE_DELAY_SECS / 4);
+
+        let capped = retry_delay(30);
+        assert!(capped.num_seconds() <= RETRY_MAX_DELAY_SECS + RETRY_MAX_DELAY_SECS / 4);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::error::Error;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Grid layout of the scrubbing-preview sprite sheet
+const SPRITE_COLUMNS: u32 = 10;
+const SPRITE_ROWS: u32 = 10;
+const SPRITE_TILE_WIDTH: u32 = 160;
+const SPRITE_TILE_HEIGHT: u32 = 90;
+
+/// Where a video's poster frame and scrubbing sprite/VTT ended up on disk
+pub(crate) struct ThumbnailArtifacts {
+    pub(crate) poster_path: PathBuf,
+    pub(crate) sprite_path: PathBuf,
+    pub(crate) vtt_path: PathBuf,
+}
+
+/// Extracts a poster frame (at 10% of the video's duration) and a scrubbing-preview
+/// sprite sheet with matching WebVTT cue file, writing both into `output_dir`
+pub(crate) fn generate_thumbnails(
+    ffmpeg_location: &str,
+    ffprobe_location: &str,
+    source_path: &str,
+    output_dir: &Path,
+) -> Result<ThumbnailArtifacts, Error> {
+    let duration_secs = probe_duration_seconds(ffprobe_location, source_path).ok_or_else(|| {
+        Error::VideoProcessingError("Could not determine source video duration".to_string())
+    })?;
+
+    let poster_path = output_dir.join("poster.jpg");
+    extract_poster_frame(ffmpeg_location, source_path, duration_secs, &poster_path)?;
+
+    let sprite_path = output_dir.join("sprite.jpg");
+    let tile_count = (SPRITE_COLUMNS * SPRITE_ROWS) as f64;
+    let interval_secs = (duration_secs / tile_count).max(1.0);
+    extract_sprite_sheet(ffmpeg_location, source_path, interval_secs, &sprite_path)?;
+
+    let vtt_path = output_dir.join("thumbnails.vtt");
+    write_sprite_vtt(&vtt_path, duration_secs, interval_secs)?;
+
+    Ok(ThumbnailArtifacts {
+        poster_path,
+        sprite_path,
+        vtt_path,
+    })
+}
+
+/// Reads the source's duration (in seconds) via `ffprobe -show_entries format=duration`
+fn probe_duration_seconds(ffprobe_location: &str, path: &str) -> Option<f64> {
+    let output = Command::new(ffprobe_location)
+        .args([
+            "-v",
+            "quiet",
+            "-print_format",
+            "json",
+            "-show_entries",
+            "format=duration",
+            path,
+        ])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let raw = String::from_utf8(output.stdout).ok()?;
+    let parsed: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    parsed
+        .get("format")?
+        .get("duration")?
+        .as_str()?
+        .parse::<f64>()
+        .ok()
+}
+
+fn extract_poster_frame(
+    ffmpeg_location: &str,
+    source_path: &str,
+    duration_secs: f64,
+    poster_path: &Path,
+) -> Result<(), Error> {
+    let poster_time = duration_secs * 0.1;
+    let status = Command::new(ffmpeg_location)
+        .args([
+            "-y",
+            "-ss",
+            &format!("{poster_time:.3}"),
+            "-i",
+            source_path,
+            "-frames:v",
+            "1",
+            poster_path
+                .to_str()
+                .expect("poster path should be valid UTF-8"),
+        ])
+        .status()
+        .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+
+    if !status.success() {
+        return Err(Error::VideoProcessingError(
+            "ffmpeg failed to extract poster frame".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn extract_sprite_sheet(
+    ffmpeg_location: &str,
+    source_path: &str,
+    interval_secs: f64,
+    sprite_path: &Path,
+) -> Result<(), Error> {
+    let filter = format!(
+        "fps=1/{interval_secs:.3},scale={SPRITE_TILE_WIDTH}:{SPRITE_TILE_HEIGHT},tile={SPRITE_COLUMNS}x{SPRITE_ROWS}"
+    );
+    let status = Command::new(ffmpeg_location)
+        .args([
+            "-y",
+            "-i",
+            source_path,
+            "-vf",
+            &filter,
+            "-frames:v",
+            "1",
+            sprite_path
+                .to_str()
+                .expect("sprite path should be valid UTF-8"),
+        ])
+        .status()
+        .map_err(|e| Error::VideoProcessingError(e.to_string()))?;
+
+    if !status.success() {
+        return Err(Error::VideoProcessingError(
+            "ffmpeg failed to generate sprite sheet".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Writes a WebVTT file mapping each sprite interval to its tile's `x,y,w,h` rect
+fn write_sprite_vtt(vtt_path: &Path, duration_secs: f64, interval_secs: f64) -> Result<(), Error> {
+    let mut vtt = String::from("WEBVTT\n\n");
+    let tile_count = SPRITE_COLUMNS * SPRITE_ROWS;
+
+    let mut index = 0u32;
+    let mut start = 0.0f64;
+    while start < duration_secs && index < tile_count {
+        let end = (start + interval_secs).min(duration_secs);
+        let column = index % SPRITE_COLUMNS;
+        let row = index / SPRITE_COLUMNS;
+        let x = column * SPRITE_TILE_WIDTH;
+        let y = row * SPRITE_TILE_HEIGHT;
+
+        writeln!(
+            vtt,
+            "{}\nsprite.jpg#xywh={x},{y},{SPRITE_TILE_WIDTH},{SPRITE_TILE_HEIGHT}\n",
+            format_vtt_range(start, end)
+        )
+        .expect("writing to a String cannot fail");
+
+        start = end;
+        index += 1;
+    }
+
+    std::fs::write(vtt_path, vtt).map_err(|e| Error::VideoProcessingError(e.to_string()))
+}
+
+fn format_vtt_range(start: f64, end: f64) -> String {
+    format!(
+        "{} --> {}",
+        format_vtt_timestamp(start),
+        format_vtt_timestamp(end)
+    )
+}
+
+fn format_vtt_timestamp(total_secs: f64) -> String {
+    let total_millis = (total_secs * 1000.0).round() as u64;
+    let hours = total_millis / 3_600_000;
+    let minutes = (total_millis % 3_600_000) / 60_000;
+    let secs = (total_millis % 60_000) / 1000;
+    let millis = total_millis % 1000;
+    format!("{hours:02}:{minutes:02}:{secs:02}.{millis:03}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_vtt_timestamp_handles_zero() {
+        assert_eq!(format_vtt_timestamp(0.0), "00:00:00.000");
+    }
+
+    #[test]
+    fn format_vtt_timestamp_handles_sub_second_precision() {
+        assert_eq!(format_vtt_timestamp(1.234), "00:00:01.234");
+    }
+
+    #[test]
+    fn format_vtt_timestamp_rolls_over_minutes_and_hours() {
+        assert_eq!(format_vtt_timestamp(61.5), "00:01:01.500");
+        assert_eq!(format_vtt_timestamp(3661.0), "01:01:01.000");
+    }
+
+    #[test]
+    fn format_vtt_range_joins_two_timestamps() {
+        assert_eq!(
+            format_vtt_range(1.0, 2.5),
+            "00:00:01.000 --> 00:00:02.500"
+        );
+    }
+}